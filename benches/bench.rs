@@ -0,0 +1,99 @@
+//! Wall-clock benchmark for the optimizing IR pass (run-length coalescing
+//! and multiply-loop recognition in `lang::push_add`/`lang::recognize_loop`),
+//! comparing the optimizing `VM` against a naive, byte-by-byte reference
+//! interpreter on programs the optimizer targets.
+//!
+//! This repo has no `Cargo.toml`, so there's no `cargo bench` target to hang
+//! this off of; it's a standalone binary instead, built the same way the
+//! rest of the tree is exercised without Cargo. The `bench` cfg switches on
+//! `lang::naive_interpret` (normally test-only), so this reuses the exact
+//! reference interpreter the unit tests check against instead of a second,
+//! driftable copy:
+//!
+//!     rustc --edition 2021 -O --cfg bench benches/bench.rs -o /tmp/bf_bench && /tmp/bf_bench
+
+#[path = "../src/lang.rs"]
+mod lang;
+
+use lang::naive_interpret;
+use lang::VmConfig;
+use lang::VM;
+use std::io::Cursor;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Timed iterations per case, after `WARMUP_ITERATIONS` untimed ones. A
+/// single-shot timing is dominated by scheduling/cache noise on the
+/// sub-millisecond cases here, so each side is run repeatedly and averaged.
+const TIMED_ITERATIONS: u32 = 20;
+const WARMUP_ITERATIONS: u32 = 3;
+
+fn vm_interpret(src: &str) -> Vec<u8> {
+    let mut vm = VM::construct(src, VmConfig::default()).expect("benchmark source is valid BF");
+    let mut output: Vec<u8> = Vec::new();
+    vm.run(&mut Cursor::new(Vec::new()), &mut output)
+        .expect("benchmark program does not overflow");
+    return output;
+}
+
+/// Runs `f` `WARMUP_ITERATIONS + TIMED_ITERATIONS` times, discarding the
+/// warmup, and returns its last output plus the mean duration of the timed
+/// runs.
+fn time_it<F: Fn() -> Vec<u8>>(f: F) -> (Vec<u8>, Duration) {
+    for _ in 0..WARMUP_ITERATIONS {
+        f();
+    }
+    let mut total = Duration::ZERO;
+    let mut out = Vec::new();
+    for _ in 0..TIMED_ITERATIONS {
+        let start = Instant::now();
+        out = f();
+        total += start.elapsed();
+    }
+    return (out, total / TIMED_ITERATIONS);
+}
+
+struct Case {
+    name: &'static str,
+    src: String,
+}
+
+fn main() {
+    let cases = vec![
+        Case {
+            name: "tight add run (50k consecutive '+')",
+            src: "+".repeat(50_000) + ".",
+        },
+        Case {
+            name: "tight move run (50k consecutive '>')",
+            src: ">".repeat(50_000) + "+.",
+        },
+        Case {
+            name: "set-zero idiom in a loop (5k '[-]')",
+            src: "+".repeat(200) + &"[-]+".repeat(5_000) + ".",
+        },
+        Case {
+            name: "multiply loop (x200, repeated 2k times)",
+            src: ("+[>".to_string() + &"+".repeat(200) + "<-]" + ">.<").repeat(2_000),
+        },
+    ];
+
+    println!(
+        "{:<42} {:>14} {:>14} {:>10}",
+        "case (mean of 20 runs)", "naive", "vm", "speedup"
+    );
+    for case in cases {
+        let (naive_out, naive_time) = time_it(|| naive_interpret(&case.src, &[]));
+        let (vm_out, vm_time) = time_it(|| vm_interpret(&case.src));
+        assert_eq!(
+            naive_out, vm_out,
+            "VM output diverged from the naive interpreter for '{}'",
+            case.name
+        );
+        let speedup = naive_time.as_secs_f64() / vm_time.as_secs_f64().max(1e-9);
+        println!(
+            "{:<42} {:>14?} {:>14?} {:>9.1}x",
+            case.name, naive_time, vm_time, speedup
+        );
+    }
+}
@@ -1,13 +1,44 @@
+use crate::encoding;
+use crate::encoding::Encoding;
+use crate::lang::CellWidth;
+use crate::lang::EofBehavior;
+use crate::lang::Overflow;
+use crate::lang::VmConfig;
+use crate::lang::VmError;
 use crate::lang::VM;
 use std::collections::VecDeque;
 use std::env;
+use std::io::Read;
 use std::io::Write;
 
+/// Tees writes out to every writer in the list, e.g. printing to the screen
+/// and saving to a file at the same time.
+struct Tee(Vec<Box<dyn Write>>);
+
+impl Write for Tee {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for writer in self.0.iter_mut() {
+            writer.write_all(buf)?;
+        }
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        for writer in self.0.iter_mut() {
+            writer.flush()?;
+        }
+        return Ok(());
+    }
+}
+
 /// Returned when CLI app fails.
+#[derive(Debug)]
 pub enum CliError {
     Arguments(&'static str),
     File(std::io::Error),
-    Syntax,
+    Syntax(String),
+    Runtime(&'static str),
+    Encoding(String),
 }
 
 struct CliArgs {
@@ -17,11 +48,31 @@ struct CliArgs {
     input_file: Option<String>,
     input_stream: Option<String>,
     print_screen: bool,
+    cell_width: CellWidth,
+    overflow: Overflow,
+    eof: EofBehavior,
+    input_encoding: Encoding,
+    output_encoding: Encoding,
+    ignore_garbage: bool,
+    dry_run: bool,
+    verbose: bool,
 }
 
 impl CliArgs {
     /// Parses given cli arguments into a static struct with convenience methods.
+    ///
+    /// Flags may be given as `--flag value`, `--flag=value`, or clustered
+    /// short flags (e.g. `-pv`); `--` stops flag parsing, treating everything
+    /// after it as positional. Exactly one positional argument, the source
+    /// file, is expected.
     fn parse() -> Result<CliArgs, CliError> {
+        return Self::parse_from(env::args().collect());
+    }
+
+    /// The actual parsing logic, taking arguments directly rather than from
+    /// `env::args()`, so it can be exercised in tests without a real process.
+    fn parse_from(args: Vec<String>) -> Result<CliArgs, CliError> {
+        let config = VmConfig::default();
         let mut cli_args = CliArgs {
             path: "".into(),
             source_file: "".into(),
@@ -29,8 +80,16 @@ impl CliArgs {
             input_file: None,
             input_stream: None,
             print_screen: false,
+            cell_width: config.cell_width,
+            overflow: config.overflow,
+            eof: config.eof,
+            input_encoding: Encoding::Raw,
+            output_encoding: Encoding::Raw,
+            ignore_garbage: false,
+            dry_run: false,
+            verbose: false,
         };
-        let mut args: VecDeque<String> = env::args().collect();
+        let mut args: VecDeque<String> = args.into();
 
         // Argument #0 is always provided by the OS, pointing to where the executable runs.
         // CLI params come from #1 etc.
@@ -49,71 +108,51 @@ impl CliArgs {
             return Err(CliError::Arguments(usage));
         }
 
-        // Returns with usage if '--help' or '-h' flag.
-        for v in args.iter() {
-            if v == "--help" || v == "-h" {
-                let usage = include_str!("help");
-                return Err(CliError::Arguments(usage));
-            }
-        }
+        let mut positional: Vec<String> = Vec::new();
+        let mut only_positional = false;
 
-        // Searches for output file flag, '--output' or '-o' then filename.
-        for (i, v) in args.iter().enumerate() {
-            if v == "--output" || v == "-o" {
-                if i + 1 < args.len() {
-                    cli_args.output_file = args.remove(i + 1);
-                    args.remove(i);
-                    break;
-                } else {
-                    return Err(CliError::Arguments("Missing output file name."));
-                }
+        while let Some(token) = args.pop_front() {
+            if only_positional {
+                positional.push(token);
+                continue;
             }
-        }
-
-        // Searches for input file flag, '--input' or '-i' then filename.
-        for (i, v) in args.iter().enumerate() {
-            if v == "--input" || v == "-i" {
-                if i + 1 < args.len() {
-                    cli_args.input_file = args.remove(i + 1);
-                    args.remove(i);
-                    break;
-                } else {
-                    return Err(CliError::Arguments("Missing input file name."));
-                }
+            if token == "--" {
+                only_positional = true;
+                continue;
             }
-        }
-
-        // Searches for input stream flag, '--stream' or '-s' then stream.
-        for (i, v) in args.iter().enumerate() {
-            if v == "--stream" || v == "-s" {
-                if i + 1 < args.len() {
-                    cli_args.input_stream = args.remove(i + 1);
-                    args.remove(i);
-                    break;
-                } else {
-                    return Err(CliError::Arguments("Missing stream."));
-                }
+            // A lone "-" is the conventional stand-in for stdin (see
+            // `load_source`/`open_input`), not a flag with an empty name.
+            if token == "-" {
+                positional.push(token);
+                continue;
             }
-        }
-
-        // Searches for print flag, '--print' or '-p'.
-        for (i, v) in args.iter().enumerate() {
-            if v == "--print" || v == "-p" {
-                cli_args.print_screen = true;
-                args.remove(i);
-                break;
+            if let Some(rest) = token.strip_prefix("--") {
+                let (name, inline_value) = match rest.split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (rest, None),
+                };
+                cli_args.apply_long_flag(name, inline_value, &mut args)?;
+            } else if let Some(rest) = token.strip_prefix('-') {
+                if rest.is_empty() {
+                    return Err(CliError::Arguments("Unrecognized flag '-'."));
+                }
+                cli_args.apply_short_cluster(rest, &mut args)?;
+            } else {
+                positional.push(token);
             }
         }
 
-        // Reads last remaining argument as source file.
-        if args.len() == 1 {
-            cli_args.source_file = args.remove(0).unwrap();
-        } else {
+        // Reads the single remaining positional argument as source file.
+        if positional.len() == 1 {
+            cli_args.source_file = positional.remove(0);
+        } else if positional.is_empty() {
             return Err(CliError::Arguments("Missing source file name."));
+        } else {
+            return Err(CliError::Arguments("Too many positional arguments."));
         }
 
-        // Returns error if output is not being printed or saved to file.
-        if !cli_args.print_screen && cli_args.output_file == None {
+        // Returns error if output is not being printed, saved to file, or a dry run.
+        if !cli_args.print_screen && cli_args.output_file == None && !cli_args.dry_run {
             return Err(CliError::Arguments("No output file or print flag."));
         }
 
@@ -121,67 +160,304 @@ impl CliArgs {
         return Ok(cli_args);
     }
 
-    /// Reads source code from target file.
-    fn load_source(&self) -> Result<String, std::io::Error> {
-        match std::fs::read_to_string(&self.source_file) {
-            Ok(src) => {
-                return Ok(src);
+    /// Applies a long flag (`--name` or `--name=value`), pulling the value
+    /// from `inline_value` if given, or the next token in `args` otherwise.
+    fn apply_long_flag(
+        &mut self,
+        name: &str,
+        inline_value: Option<String>,
+        args: &mut VecDeque<String>,
+    ) -> Result<(), CliError> {
+        match name {
+            "help" => {
+                let usage = include_str!("help");
+                return Err(CliError::Arguments(usage));
             }
-            Err(err) => {
-                return Err(err);
+            "print" => {
+                self.no_value(inline_value, "--print")?;
+                self.print_screen = true;
+            }
+            "dry-run" => {
+                self.no_value(inline_value, "--dry-run")?;
+                self.dry_run = true;
+            }
+            "verbose" => {
+                self.no_value(inline_value, "--verbose")?;
+                self.verbose = true;
+            }
+            "ignore-garbage" => {
+                self.no_value(inline_value, "--ignore-garbage")?;
+                self.ignore_garbage = true;
+            }
+            "output" => {
+                self.output_file = Some(self.take_value(inline_value, args, "--output")?);
+            }
+            "input" => {
+                self.input_file = Some(self.take_value(inline_value, args, "--input")?);
+            }
+            "stream" => {
+                self.input_stream = Some(self.take_value(inline_value, args, "--stream")?);
+            }
+            "cell-width" => {
+                let value = self.take_value(inline_value, args, "--cell-width")?;
+                self.cell_width = match value.as_str() {
+                    "8" => CellWidth::U8,
+                    "16" => CellWidth::U16,
+                    "32" => CellWidth::U32,
+                    _ => {
+                        return Err(CliError::Arguments("Invalid cell width, expected 8, 16 or 32."));
+                    }
+                };
+            }
+            "overflow" => {
+                let value = self.take_value(inline_value, args, "--overflow")?;
+                self.overflow = match value.as_str() {
+                    "wrap" => Overflow::Wrap,
+                    "saturate" => Overflow::Saturate,
+                    "halt" => Overflow::Halt,
+                    _ => {
+                        return Err(CliError::Arguments(
+                            "Invalid overflow behavior, expected wrap, saturate or halt.",
+                        ));
+                    }
+                };
+            }
+            "eof" => {
+                let value = self.take_value(inline_value, args, "--eof")?;
+                self.eof = match value.as_str() {
+                    "unchanged" => EofBehavior::Unchanged,
+                    "zero" => EofBehavior::Zero,
+                    "neg-one" => EofBehavior::NegOne,
+                    _ => {
+                        return Err(CliError::Arguments(
+                            "Invalid EOF behavior, expected unchanged, zero or neg-one.",
+                        ));
+                    }
+                };
+            }
+            "input-encoding" => {
+                let value = self.take_value(inline_value, args, "--input-encoding")?;
+                self.input_encoding = match value.as_str() {
+                    "raw" => Encoding::Raw,
+                    "base32" => Encoding::Base32,
+                    "base64" => Encoding::Base64,
+                    _ => {
+                        return Err(CliError::Arguments(
+                            "Invalid input encoding, expected raw, base32 or base64.",
+                        ));
+                    }
+                };
+            }
+            "output-encoding" => {
+                let value = self.take_value(inline_value, args, "--output-encoding")?;
+                self.output_encoding = match value.as_str() {
+                    "raw" => Encoding::Raw,
+                    "base32" => Encoding::Base32,
+                    "base64" => Encoding::Base64,
+                    _ => {
+                        return Err(CliError::Arguments(
+                            "Invalid output encoding, expected raw, base32 or base64.",
+                        ));
+                    }
+                };
+            }
+            _ => {
+                return Err(CliError::Arguments("Unrecognized flag."));
             }
         }
+        return Ok(());
     }
 
-    /// Returns input buffer. It consists of the stream then bytecode from input file.
-    fn fetch_input(&self) -> Result<VecDeque<u8>, std::io::Error> {
-        let mut input: VecDeque<u8> = VecDeque::new();
-        if let Some(stream) = &self.input_stream {
-            for c in stream.chars() {
-                input.push_back(c as u8);
-            }
-        }
-        if let Some(file) = &self.input_file {
-            match std::fs::read(file) {
-                Ok(bytes) => {
-                    for b in bytes {
-                        input.push_back(b);
-                    }
+    /// Applies a cluster of short flags, e.g. `"pv"` from `-pv`. Only the
+    /// last flag in a cluster may take a value, taken from the rest of the
+    /// cluster if any remains, or the next whole argument otherwise.
+    fn apply_short_cluster(&mut self, cluster: &str, args: &mut VecDeque<String>) -> Result<(), CliError> {
+        let chars: Vec<char> = cluster.chars().collect();
+        for (i, c) in chars.iter().enumerate() {
+            let remainder: String = chars[i + 1..].iter().collect();
+            match c {
+                'h' => {
+                    let usage = include_str!("help");
+                    return Err(CliError::Arguments(usage));
+                }
+                'p' => {
+                    self.print_screen = true;
+                }
+                'v' => {
+                    self.verbose = true;
+                }
+                'o' => {
+                    self.output_file = Some(self.take_cluster_value(remainder, args, "-o")?);
+                    return Ok(());
+                }
+                'i' => {
+                    self.input_file = Some(self.take_cluster_value(remainder, args, "-i")?);
+                    return Ok(());
+                }
+                's' => {
+                    self.input_stream = Some(self.take_cluster_value(remainder, args, "-s")?);
+                    return Ok(());
+                }
+                'w' => {
+                    let value = self.take_cluster_value(remainder, args, "-w")?;
+                    self.cell_width = match value.as_str() {
+                        "8" => CellWidth::U8,
+                        "16" => CellWidth::U16,
+                        "32" => CellWidth::U32,
+                        _ => {
+                            return Err(CliError::Arguments("Invalid cell width, expected 8, 16 or 32."));
+                        }
+                    };
+                    return Ok(());
                 }
-                Err(err) => {
-                    return Err(err);
+                _ => {
+                    return Err(CliError::Arguments("Unrecognized flag."));
                 }
             }
         }
-        return Ok(input);
+        return Ok(());
     }
 
-    /// Writes data to output file, if provided.
-    fn flush_output(&self, data: &[u8]) -> Result<(), std::io::Error> {
-        match &self.output_file {
-            Some(file) => match std::fs::File::create(file) {
-                Ok(mut stream) => match stream.write_all(data) {
-                    Ok(_) => {
-                        return Ok(());
-                    }
-                    Err(err) => {
-                        return Err(err);
-                    }
-                },
-                Err(err) => {
-                    return Err(err);
-                }
-            },
+    /// Rejects an inline value (`--flag=value`) on a flag that takes none.
+    fn no_value(&self, inline_value: Option<String>, flag: &'static str) -> Result<(), CliError> {
+        match inline_value {
+            Some(_) => {
+                return Err(CliError::Arguments("Flag does not take a value."));
+            }
             None => {
+                let _ = flag;
                 return Ok(());
             }
         }
     }
+
+    /// Resolves a value-taking long flag's value: the inline `--flag=value`
+    /// text if given, otherwise the next whole token in `args`.
+    fn take_value(
+        &self,
+        inline_value: Option<String>,
+        args: &mut VecDeque<String>,
+        flag: &'static str,
+    ) -> Result<String, CliError> {
+        if let Some(value) = inline_value {
+            return Ok(value);
+        }
+        match args.pop_front() {
+            Some(value) => {
+                return Ok(value);
+            }
+            None => {
+                let _ = flag;
+                return Err(CliError::Arguments("Missing value for flag."));
+            }
+        }
+    }
+
+    /// Resolves a value-taking short flag's value: the remainder of its
+    /// cluster if non-empty (e.g. `8` in `-w8`), otherwise the next whole
+    /// token in `args`.
+    fn take_cluster_value(
+        &self,
+        remainder: String,
+        args: &mut VecDeque<String>,
+        flag: &'static str,
+    ) -> Result<String, CliError> {
+        if !remainder.is_empty() {
+            return Ok(remainder);
+        }
+        match args.pop_front() {
+            Some(value) => {
+                return Ok(value);
+            }
+            None => {
+                let _ = flag;
+                return Err(CliError::Arguments("Missing value for flag."));
+            }
+        }
+    }
+
+    /// Collects the dialect flags into a `VmConfig`.
+    fn config(&self) -> VmConfig {
+        return VmConfig {
+            cell_width: self.cell_width,
+            overflow: self.overflow,
+            eof: self.eof,
+        };
+    }
+
+    /// Reads source code from the target file, or from stdin if it is `-`.
+    fn load_source(&self) -> Result<String, std::io::Error> {
+        if self.source_file == "-" {
+            let mut src = String::new();
+            std::io::stdin().read_to_string(&mut src)?;
+            return Ok(src);
+        }
+        return std::fs::read_to_string(&self.source_file);
+    }
+
+    /// Opens the VM's input as a stream: the `--stream`/`-s` bytes first,
+    /// then the `--input`/`-i` file, or stdin if that path is `-`.
+    ///
+    /// With `--input-encoding raw` (the default), stdin is read lazily byte
+    /// by byte, so interactive programs can prompt and read a reply in real
+    /// time. A `base32`/`base64` encoding instead buffers the whole stream
+    /// up front, since decoding needs it in full anyway.
+    fn open_input(&self) -> Result<Box<dyn Read>, CliError> {
+        if self.input_encoding == Encoding::Raw {
+            let stream: Box<dyn Read> = match &self.input_stream {
+                Some(stream) => {
+                    let bytes: Vec<u8> = stream.chars().map(|c| c as u8).collect();
+                    Box::new(std::io::Cursor::new(bytes))
+                }
+                None => Box::new(std::io::empty()),
+            };
+            let file: Box<dyn Read> = match &self.input_file {
+                Some(file) if file == "-" => Box::new(std::io::stdin()),
+                Some(file) => Box::new(std::fs::File::open(file).map_err(CliError::File)?),
+                None => Box::new(std::io::empty()),
+            };
+            return Ok(Box::new(stream.chain(file)));
+        }
+
+        let mut text: Vec<u8> = Vec::new();
+        if let Some(stream) = &self.input_stream {
+            text.extend(stream.chars().map(|c| c as u8));
+        }
+        match &self.input_file {
+            Some(file) if file == "-" => {
+                std::io::stdin()
+                    .read_to_end(&mut text)
+                    .map_err(CliError::File)?;
+            }
+            Some(file) => {
+                text.extend(std::fs::read(file).map_err(CliError::File)?);
+            }
+            None => {}
+        }
+
+        let bytes = encoding::decode(self.input_encoding, &text, self.ignore_garbage)
+            .map_err(|err| CliError::Encoding(err.message))?;
+        return Ok(Box::new(std::io::Cursor::new(bytes)));
+    }
+
+    /// Opens the VM's output as a sink: the screen if `--print`/`-p` was
+    /// given, the `--output`/`-o` file if one was given, or both.
+    fn open_output(&self) -> Result<Tee, std::io::Error> {
+        let mut writers: Vec<Box<dyn Write>> = Vec::new();
+        if self.print_screen {
+            writers.push(Box::new(std::io::stdout()));
+        }
+        if let Some(file) = &self.output_file {
+            writers.push(Box::new(std::fs::File::create(file)?));
+        }
+        return Ok(Tee(writers));
+    }
 }
 
+
 /// Parses CLI arguments and runs the required functionality.
-/// Returns the output on success, or CliError otherwise.
-pub fn execute() -> Result<Vec<u8>, CliError> {
+/// Returns once the VM has halted, or CliError otherwise.
+pub fn execute() -> Result<(), CliError> {
     // Parses cli params.
     let args: CliArgs;
     match CliArgs::parse() {
@@ -206,43 +482,185 @@ pub fn execute() -> Result<Vec<u8>, CliError> {
 
     // Constructs a new VM from source.
     let mut vm: VM;
-    match VM::construct(&source) {
+    match VM::construct(&source, args.config()) {
         Ok(v) => {
             vm = v;
         }
-        Err(_) => {
-            return Err(CliError::Syntax);
+        Err(err) => {
+            return Err(CliError::Syntax(err.describe(&source)));
         }
     }
 
-    // Gets the input buffer.
-    let mut input: VecDeque<u8>;
-    match args.fetch_input() {
-        Ok(b) => {
-            input = b;
+    // A dry run only compiles: report the instruction count and stop, since
+    // a program that reached this point already has balanced brackets.
+    if args.dry_run {
+        println!(
+            "Syntax OK. Brackets balanced. Compiled to {} instruction(s).",
+            vm.instruction_count()
+        );
+        return Ok(());
+    }
+
+    // Opens the input stream.
+    let mut input: Box<dyn Read>;
+    match args.open_input() {
+        Ok(r) => {
+            input = r;
         }
         Err(err) => {
-            return Err(CliError::File(err));
+            return Err(err);
         }
     }
 
-    // Runs the VM.
-    let mut output: Vec<u8> = Vec::new();
-    vm.run(&mut input, &mut output);
-
-    // Prints output to screen, if requested.
-    if args.print_screen {
-        println!("{}", output.iter().map(|b| *b as char).collect::<String>());
+    // An encoded output can only be produced once the whole program has
+    // run, so it is buffered; raw output is instead streamed as it's
+    // produced, straight to the screen and/or file sink.
+    if args.output_encoding == encoding::Encoding::Raw {
+        let mut output: Tee;
+        match args.open_output() {
+            Ok(w) => {
+                output = w;
+            }
+            Err(err) => {
+                return Err(CliError::File(err));
+            }
+        }
+        match vm.run(&mut input, &mut output) {
+            Ok(_) => {}
+            Err(VmError::Io(err)) => {
+                return Err(CliError::File(err));
+            }
+            Err(VmError::Overflow) => {
+                return Err(CliError::Runtime("Halted: a cell overflowed its configured width."));
+            }
+        }
+    } else {
+        let mut buffer: Vec<u8> = Vec::new();
+        match vm.run(&mut input, &mut buffer) {
+            Ok(_) => {}
+            Err(VmError::Io(err)) => {
+                return Err(CliError::File(err));
+            }
+            Err(VmError::Overflow) => {
+                return Err(CliError::Runtime("Halted: a cell overflowed its configured width."));
+            }
+        }
+        let encoded = encoding::encode(args.output_encoding, &buffer);
+        let mut output: Tee;
+        match args.open_output() {
+            Ok(w) => {
+                output = w;
+            }
+            Err(err) => {
+                return Err(CliError::File(err));
+            }
+        }
+        match output.write_all(&encoded) {
+            Ok(_) => {}
+            Err(err) => {
+                return Err(CliError::File(err));
+            }
+        }
     }
 
-    // Writes output to file, if requested.
-    match args.flush_output(&output) {
-        Ok(_) => {}
-        Err(err) => {
-            return Err(CliError::File(err));
-        }
+    // Reports execution stats, if requested.
+    if args.verbose {
+        print_stats(&vm);
     }
 
     // Done!
-    return Ok(output);
+    return Ok(());
+}
+
+/// Prints the VM's accumulated execution stats for `--verbose`.
+fn print_stats(vm: &VM) {
+    let stats = vm.stats();
+    println!("--- execution stats ---");
+    println!("steps:          {}", stats.steps);
+    println!("  move:         {}", stats.move_count);
+    println!("  add:          {}", stats.add_count);
+    println!("  set-zero:     {}", stats.set_zero_count);
+    println!("  mul-add:      {}", stats.mul_add_count);
+    println!("  out:          {}", stats.out_count);
+    println!("  in:           {}", stats.in_count);
+    println!("  jump:         {}", stats.jump_count);
+    println!("  loop:         {}", stats.loop_count);
+    println!("peak tape size: {}", stats.peak_tape_size);
+    println!("final dp:       {}", vm.dp());
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a `parse_from` argument list, standing in for `env::args()`,
+    /// with the conventional argv[0] prepended.
+    fn args(rest: &[&str]) -> Vec<String> {
+        let mut v: Vec<String> = vec!["brainrust".to_string()];
+        v.extend(rest.iter().map(|s| s.to_string()));
+        return v;
+    }
+
+    #[test]
+    fn clustered_short_flags_combine() {
+        // "-ps" sets print_screen ('p') and reads the stream value for 's'
+        // from the next whole token, since nothing remains in the cluster.
+        let parsed = CliArgs::parse_from(args(&["-ps", "hello", "prog.bf"])).unwrap();
+        assert!(parsed.print_screen);
+        assert_eq!(parsed.input_stream, Some("hello".to_string()));
+        assert_eq!(parsed.source_file, "prog.bf");
+    }
+
+    #[test]
+    fn clustered_short_flag_value_from_remainder() {
+        // "-w8" reads cell width "8" from the rest of the cluster, not the
+        // next token.
+        let parsed = CliArgs::parse_from(args(&["-pw8", "prog.bf"])).unwrap();
+        assert!(parsed.print_screen);
+        assert!(matches!(parsed.cell_width, CellWidth::U8));
+    }
+
+    #[test]
+    fn repeated_flags_are_idempotent() {
+        let parsed = CliArgs::parse_from(args(&["-p", "-p", "--verbose", "--verbose", "prog.bf"])).unwrap();
+        assert!(parsed.print_screen);
+        assert!(parsed.verbose);
+    }
+
+    #[test]
+    fn long_flag_with_inline_equals_value() {
+        let parsed = CliArgs::parse_from(args(&["--output=out.bin", "--dry-run", "prog.bf"])).unwrap();
+        assert_eq!(parsed.output_file, Some("out.bin".to_string()));
+    }
+
+    #[test]
+    fn long_flag_with_separate_value() {
+        let parsed = CliArgs::parse_from(args(&["--overflow", "saturate", "-p", "prog.bf"])).unwrap();
+        assert!(matches!(parsed.overflow, Overflow::Saturate));
+    }
+
+    #[test]
+    fn dry_run_flag_skips_output_requirement() {
+        // Normally at least one of --print/--output is required; --dry-run
+        // is its own exemption, since it never produces output.
+        let parsed = CliArgs::parse_from(args(&["--dry-run", "prog.bf"])).unwrap();
+        assert!(parsed.dry_run);
+    }
+
+    #[test]
+    fn lone_dash_after_double_dash_is_positional() {
+        let parsed = CliArgs::parse_from(args(&["-p", "--", "-"])).unwrap();
+        assert_eq!(parsed.source_file, "-");
+    }
+
+    #[test]
+    fn unrecognized_flag_in_cluster_is_an_error() {
+        let result = CliArgs::parse_from(args(&["-pz", "prog.bf"]));
+        assert!(matches!(result, Err(CliError::Arguments(_))));
+    }
+
+    #[test]
+    fn missing_value_for_long_flag_is_an_error() {
+        let result = CliArgs::parse_from(args(&["--output"]));
+        assert!(matches!(result, Err(CliError::Arguments(_))));
+    }
 }
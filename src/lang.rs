@@ -1,14 +1,236 @@
 use std::collections::VecDeque;
 
+/// Tree-shaped intermediate representation produced by parsing.
+///
+/// Unlike the flat, one-instruction-per-character form ultimately executed by
+/// the VM, a `Node::Loop` nests its body so that optimization passes can
+/// recognize structural idioms (e.g. "clear cell" or "multiply into cell")
+/// before the program is flattened into `Instruction`s.
+#[derive(Clone, PartialEq)]
+enum Node {
+    Move(isize),
+    Add(i64),
+    Out,
+    In,
+    Loop(Vec<Node>),
+    SetZero,
+    MulAdd(Vec<(isize, i64)>),
+}
+
+/// Coalesces a `>`/`<` run into `nodes`, merging with a trailing `Move` if
+/// present and dropping the node entirely if it cancels out to zero.
+fn push_move(nodes: &mut Vec<Node>, delta: isize) {
+    if let Some(Node::Move(prev)) = nodes.last_mut() {
+        let sum = *prev + delta;
+        if sum == 0 {
+            nodes.pop();
+        } else {
+            *prev = sum;
+        }
+        return;
+    }
+    nodes.push(Node::Move(delta));
+}
+
+/// Coalesces a `+`/`-` run into `nodes`, merging with a trailing `Add` only
+/// if it moves in the same direction.
+///
+/// Merging is restricted to same-direction runs (rather than any trailing
+/// `Add`, regardless of sign) so that the net delta stored in the IR is
+/// monotonic: applying it as a single step at execution time then produces
+/// exactly the same result a per-character interpreter would, under any
+/// overflow policy, not just wrapping. The full magnitude is kept (no
+/// truncation to a single byte), so cell widths wider than 8 bits see the
+/// true count too.
+fn push_add(nodes: &mut Vec<Node>, delta: i64) {
+    if let Some(Node::Add(prev)) = nodes.last_mut() {
+        if (*prev > 0 && delta > 0) || (*prev < 0 && delta < 0) {
+            *prev += delta;
+            return;
+        }
+    }
+    nodes.push(Node::Add(delta));
+}
+
+/// A location in the source, tracked as the parser walks it.
+#[derive(Clone, Copy)]
+struct Position {
+    byte_offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Position {
+    fn start() -> Self {
+        Position {
+            byte_offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /// Advances past `c`, wrapping to the next line on `\n`.
+    fn advance(&mut self, c: char) {
+        self.byte_offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+    }
+}
+
+/// Parses source into a coalesced `Node` tree.
+///
+/// `+`/`-` and `>`/`<` runs are merged as they are read. Brackets are matched
+/// with a stack of open blocks, each tagged with the position of the `[`
+/// that opened it, so a stray `]` or an unmatched `[` at the end of the
+/// source can be reported as a `SyntaxError` pointing at a real location.
+fn parse(src: &str) -> Result<Vec<Node>, SyntaxError> {
+    let mut stack: Vec<(Position, Vec<Node>)> = vec![(Position::start(), Vec::new())];
+    let mut pos = Position::start();
+
+    for c in src.chars() {
+        match c {
+            '>' => push_move(&mut stack.last_mut().unwrap().1, 1),
+            '<' => push_move(&mut stack.last_mut().unwrap().1, -1),
+            '+' => push_add(&mut stack.last_mut().unwrap().1, 1),
+            '-' => push_add(&mut stack.last_mut().unwrap().1, -1),
+            '.' => stack.last_mut().unwrap().1.push(Node::Out),
+            ',' => stack.last_mut().unwrap().1.push(Node::In),
+            '[' => {
+                stack.push((pos, Vec::new()));
+            }
+            ']' => {
+                if stack.len() <= 1 {
+                    return Err(SyntaxError::new(SyntaxErrorKind::UnmatchedClose, pos));
+                }
+                let (_, body) = stack.pop().unwrap();
+                stack.last_mut().unwrap().1.push(Node::Loop(body));
+            }
+            _ => {}
+        }
+        pos.advance(c);
+    }
+
+    if stack.len() != 1 {
+        let (open_at, _) = stack.pop().unwrap();
+        return Err(SyntaxError::new(SyntaxErrorKind::UnmatchedOpen, open_at));
+    }
+    return Ok(stack.pop().unwrap().1);
+}
+
+/// Tracks the net effect of a loop body made only of `Add`/`Move` nodes, one
+/// entry per offset from the pointer position at loop entry.
+fn add_delta(deltas: &mut Vec<(isize, i64)>, offset: isize, delta: i64) {
+    for entry in deltas.iter_mut() {
+        if entry.0 == offset {
+            entry.1 += delta;
+            return;
+        }
+    }
+    deltas.push((offset, delta));
+}
+
+/// Recognizes the "clear cell" and "multiply/copy" loop idioms.
+///
+/// `[-]`/`[+]` become `SetZero`. A balanced loop made only of `+`/`-`/`<`/`>`,
+/// with zero net pointer movement, that decrements its own cell by exactly
+/// one per iteration becomes a `MulAdd` of the net delta at every other
+/// visited offset. Anything else is left as a general `Loop`.
+fn recognize_loop(body: &[Node]) -> Option<Node> {
+    if let [Node::Add(d)] = body {
+        if *d == 1 || *d == -1 {
+            return Some(Node::SetZero);
+        }
+    }
+
+    let mut offset: isize = 0;
+    let mut deltas: Vec<(isize, i64)> = Vec::new();
+    for node in body {
+        match node {
+            Node::Add(d) => add_delta(&mut deltas, offset, *d),
+            Node::Move(d) => offset += d,
+            _ => return None,
+        }
+    }
+    if offset != 0 {
+        return None;
+    }
+
+    let mut ops: Vec<(isize, i64)> = Vec::new();
+    let mut self_delta: i64 = 0;
+    for (o, d) in deltas {
+        if o == 0 {
+            self_delta = d;
+        } else if d != 0 {
+            ops.push((o, d));
+        }
+    }
+    if self_delta != -1 {
+        return None;
+    }
+    return Some(Node::MulAdd(ops));
+}
+
+/// Walks the tree bottom-up, replacing recognized loop idioms in place.
+fn optimize(nodes: Vec<Node>) -> Vec<Node> {
+    let mut out: Vec<Node> = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        match node {
+            Node::Loop(body) => {
+                let body = optimize(body);
+                match recognize_loop(&body) {
+                    Some(idiom) => out.push(idiom),
+                    None => out.push(Node::Loop(body)),
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    return out;
+}
+
+/// Flattens the optimized tree into the linear form `VM::step` executes,
+/// back-patching each `Loop`'s matching `Jump` once its body has been
+/// emitted.
+fn flatten(nodes: &[Node], instructions: &mut Vec<Instruction>) {
+    for node in nodes {
+        match node {
+            Node::Move(d) => instructions.push(Instruction::Move(*d)),
+            Node::Add(d) => instructions.push(Instruction::Add(*d)),
+            Node::Out => instructions.push(Instruction::Out),
+            Node::In => instructions.push(Instruction::In),
+            Node::SetZero => instructions.push(Instruction::SetZero),
+            Node::MulAdd(ops) => instructions.push(Instruction::MulAdd(ops.clone())),
+            Node::Loop(body) => {
+                let jump_at = instructions.len();
+                instructions.push(Instruction::Jump(0));
+                flatten(body, instructions);
+                let loop_at = instructions.len();
+                instructions.push(Instruction::Loop(jump_at));
+                instructions[jump_at] = Instruction::Jump(loop_at);
+            }
+        }
+    }
+}
+
 /// Executable instruction for the VM.
 ///
 /// Jump and Loop include an indication of what the IP should be set to, if jumping.
-#[derive(Clone, Copy, PartialEq)]
+/// `Add`/`Move` coalesce runs of `+`/`-`/`<`/`>` into a single step; `SetZero`
+/// and `MulAdd` replace whole loops recognized as common idioms. `Add`'s
+/// delta is a full-width net count (not truncated to a byte), and is applied
+/// to the cell in one step honoring the configured overflow policy; a
+/// `Halt` policy that would trip partway through a coalesced run instead
+/// halts atomically, without partially applying it.
+#[derive(Clone, PartialEq)]
 enum Instruction {
-    Right,
-    Left,
-    Up,
-    Down,
+    Move(isize),
+    Add(i64),
+    SetZero,
+    MulAdd(Vec<(isize, i64)>),
     Out,
     In,
     Jump(usize),
@@ -16,68 +238,179 @@ enum Instruction {
     None,
 }
 
-impl Instruction {
-    /// Source character to instruction.
-    ///
-    /// All chars other than ><+-[] are ignored.
-    fn from_char(c: char) -> Self {
-        match c {
-            '>' => Instruction::Right,
-            '<' => Instruction::Left,
-            '+' => Instruction::Up,
-            '-' => Instruction::Down,
-            '.' => Instruction::Out,
-            ',' => Instruction::In,
-            '[' => Instruction::Jump(0),
-            ']' => Instruction::Loop(0),
-            _ => Instruction::None,
+/// Stable kind of syntax failure, each with its own diagnostic code.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SyntaxErrorKind {
+    UnmatchedOpen,
+    UnmatchedClose,
+}
+
+impl SyntaxErrorKind {
+    /// Stable diagnostic code, e.g. `"BR001"`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SyntaxErrorKind::UnmatchedOpen => "BR001",
+            SyntaxErrorKind::UnmatchedClose => "BR002",
+        }
+    }
+
+    /// Longer human explanation looked up from the error catalog.
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            SyntaxErrorKind::UnmatchedOpen => include_str!("errors/BR001.txt"),
+            SyntaxErrorKind::UnmatchedClose => include_str!("errors/BR002.txt"),
         }
     }
+
+    fn bracket(&self) -> char {
+        match self {
+            SyntaxErrorKind::UnmatchedOpen => '[',
+            SyntaxErrorKind::UnmatchedClose => ']',
+        }
+    }
+}
+
+/// Returned when compilation fails: what went wrong, and where in the source.
+#[derive(Debug)]
+pub struct SyntaxError {
+    pub kind: SyntaxErrorKind,
+    pub byte_offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SyntaxError {
+    fn new(kind: SyntaxErrorKind, at: Position) -> Self {
+        SyntaxError {
+            kind,
+            byte_offset: at.byte_offset,
+            line: at.line,
+            column: at.column,
+        }
+    }
+
+    /// Renders the full diagnostic: a header with the code and position, the
+    /// offending source line with a caret under the bracket, and the
+    /// catalog explanation for this kind of failure.
+    pub fn describe(&self, src: &str) -> String {
+        let line_src = src.lines().nth(self.line - 1).unwrap_or("");
+        let caret = " ".repeat(self.column.saturating_sub(1));
+        return format!(
+            "error[{}]: unmatched '{}' at line {}, column {} (byte {})\n{}\n{}^\n\n{}",
+            self.kind.code(),
+            self.kind.bracket(),
+            self.line,
+            self.column,
+            self.byte_offset,
+            line_src,
+            caret,
+            self.kind.explanation(),
+        );
+    }
 }
 
-/// Returned when compilation fails.
-pub struct SyntaxError;
+/// Cell width, i.e. the integer type a single tape cell behaves as.
+#[derive(Clone, Copy, PartialEq)]
+pub enum CellWidth {
+    U8,
+    U16,
+    U32,
+}
+
+impl CellWidth {
+    /// Largest value a cell of this width can hold.
+    fn max(&self) -> u32 {
+        match self {
+            CellWidth::U8 => u8::MAX as u32,
+            CellWidth::U16 => u16::MAX as u32,
+            CellWidth::U32 => u32::MAX,
+        }
+    }
+}
+
+/// What happens when an `Add`/`MulAdd` would push a cell outside `[0, max]`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Overflow {
+    Wrap,
+    Saturate,
+    Halt,
+}
+
+/// What a cell becomes when `In` is executed at end of input.
+#[derive(Clone, Copy, PartialEq)]
+pub enum EofBehavior {
+    Unchanged,
+    Zero,
+    NegOne,
+}
+
+/// Dialect knobs threaded through `construct` and `step`.
+#[derive(Clone, Copy)]
+pub struct VmConfig {
+    pub cell_width: CellWidth,
+    pub overflow: Overflow,
+    pub eof: EofBehavior,
+}
+
+impl Default for VmConfig {
+    /// The classic dialect: 8-bit cells, wrapping, EOF writes zero.
+    fn default() -> Self {
+        VmConfig {
+            cell_width: CellWidth::U8,
+            overflow: Overflow::Wrap,
+            eof: EofBehavior::Zero,
+        }
+    }
+}
+
+/// Returned when the VM halts on a runtime failure rather than completing.
+#[derive(Debug)]
+pub enum VmError {
+    Io(std::io::Error),
+    Overflow,
+}
+
+impl From<std::io::Error> for VmError {
+    fn from(err: std::io::Error) -> Self {
+        VmError::Io(err)
+    }
+}
+
+/// Execution counters accumulated as the VM steps, for profiling/`--verbose`.
+#[derive(Clone, Default)]
+pub struct Stats {
+    pub steps: usize,
+    pub move_count: usize,
+    pub add_count: usize,
+    pub set_zero_count: usize,
+    pub mul_add_count: usize,
+    pub out_count: usize,
+    pub in_count: usize,
+    pub jump_count: usize,
+    pub loop_count: usize,
+    pub peak_tape_size: usize,
+}
 
 /// Virtual machine.
 pub struct VM {
     ip: usize,
     dp: usize,
     instructions: Vec<Instruction>,
-    data: VecDeque<u8>,
+    data: VecDeque<u32>,
+    config: VmConfig,
+    stats: Stats,
 }
 
 impl VM {
-    /// Constructs a new valid VM from given source code.
+    /// Constructs a new valid VM from given source code and dialect config.
     ///
     /// Compilation fails if brackets are not properly paired (invalid program); a SyntaxError gets returned if so.
-    pub fn construct(src: &str) -> Result<Self, SyntaxError> {
-        let mut instructions: Vec<Instruction> = Vec::with_capacity(src.len());
-        let mut jumps: Vec<usize> = Vec::with_capacity(src.len() / 2);
-
-        // Converts source code into vector of Instructions.
-        // Caches position of each Jump in a FILO stack, that gets popped at each corresponding Loop.
-        for c in src.chars() {
-            let instr = Instruction::from_char(c);
-            if instr != Instruction::None {
-                if let Instruction::Loop(_) = instr {
-                    if let Some(loop_to) = jumps.pop() {
-                        instructions[loop_to] = Instruction::Jump(instructions.len());
-                        instructions.push(Instruction::Loop(loop_to));
-                    } else {
-                        return Err(SyntaxError);
-                    }
-                }
-                if let Instruction::Jump(_) = instr {
-                    jumps.push(instructions.len());
-                }
-                instructions.push(instr);
-            }
-        }
+    pub fn construct(src: &str, config: VmConfig) -> Result<Self, SyntaxError> {
+        let tree = parse(src)?;
+        let tree = optimize(tree);
 
-        // Syntax error if not all Jumps have matching Loop.
-        if jumps.len() > 0 {
-            return Err(SyntaxError);
-        }
+        let mut instructions: Vec<Instruction> = Vec::new();
+        flatten(&tree, &mut instructions);
 
         // Appends None. Used to halt the VM.
         instructions.push(Instruction::None);
@@ -88,73 +421,330 @@ impl VM {
             dp: 0,
             instructions,
             data: VecDeque::from(vec![0]),
+            config,
+            stats: Stats::default(),
         });
     }
 
-    /// Performs one step for the VM. It is assumed to be in a valid state.
-    ///
-    /// Returns True if None was reached, and False otherwise.
-    pub fn step(&mut self, input: &mut VecDeque<u8>, output: &mut Vec<u8>) -> bool {
-        match self.instructions[self.ip] {
-            Instruction::Right => {
-                self.dp += 1;
-                if self.data.len() == self.dp {
-                    self.data.push_back(0);
+    /// Number of instructions the source compiled down to, including the
+    /// trailing halt. Useful to report without running the program.
+    pub fn instruction_count(&self) -> usize {
+        return self.instructions.len();
+    }
+
+    /// Current data pointer position.
+    pub fn dp(&self) -> usize {
+        return self.dp;
+    }
+
+    /// Execution counters accumulated so far.
+    pub fn stats(&self) -> &Stats {
+        return &self.stats;
+    }
+
+    /// Adds `delta` to the cell at `idx`, honoring the configured cell width
+    /// and overflow behavior.
+    fn apply_delta(&mut self, idx: usize, delta: i64) -> Result<(), VmError> {
+        let max = self.config.cell_width.max() as i64;
+        let mut next = self.data[idx] as i64 + delta;
+        match self.config.overflow {
+            Overflow::Wrap => {
+                next = next.rem_euclid(max + 1);
+            }
+            Overflow::Saturate => {
+                next = next.clamp(0, max);
+            }
+            Overflow::Halt => {
+                if next < 0 || next > max {
+                    return Err(VmError::Overflow);
                 }
             }
-            Instruction::Left => {
-                if self.dp > 0 {
-                    self.dp -= 1;
-                } else {
+        }
+        self.data[idx] = next as u32;
+        return Ok(());
+    }
+
+    /// Resolves `dp + offset` to an absolute index into `data`, growing the
+    /// tape on either end as needed. If the tape is grown on the left, `dp`
+    /// itself is shifted so it keeps pointing at the same logical cell.
+    fn ensure_offset(&mut self, offset: isize) -> usize {
+        if offset >= 0 {
+            let idx = self.dp + offset as usize;
+            while self.data.len() <= idx {
+                self.data.push_back(0);
+            }
+            return idx;
+        } else {
+            let left = (-offset) as usize;
+            if left <= self.dp {
+                return self.dp - left;
+            } else {
+                let extra = left - self.dp;
+                for _ in 0..extra {
                     self.data.push_front(0);
                 }
+                self.dp += extra;
+                return self.dp - left;
             }
-            Instruction::Up => {
-                if self.data[self.dp] == u8::MAX {
-                    self.data[self.dp] = 0;
-                } else {
-                    self.data[self.dp] += 1;
-                }
+        }
+    }
+
+    /// Performs one step for the VM. It is assumed to be in a valid state.
+    ///
+    /// `In` reads a single byte from `input`, and `Out` writes a single byte to
+    /// `output` immediately, so callers can stream both rather than buffering
+    /// the whole program's input/output in memory. Arithmetic honors the
+    /// configured cell width and overflow behavior; `In` at end of input
+    /// honors the configured EOF behavior.
+    ///
+    /// Returns True if None was reached, and False otherwise.
+    pub fn step<R: std::io::Read, W: std::io::Write>(
+        &mut self,
+        input: &mut R,
+        output: &mut W,
+    ) -> Result<bool, VmError> {
+        // Cloned so the match doesn't keep `self.instructions` borrowed while
+        // `MulAdd`/`Move` need to mutate `self.data`/`self.dp` through `&mut self`.
+        match self.instructions[self.ip].clone() {
+            Instruction::Move(delta) => {
+                self.dp = self.ensure_offset(delta);
+                self.stats.move_count += 1;
             }
-            Instruction::Down => {
-                if self.data[self.dp] == 0 {
-                    self.data[self.dp] = u8::MAX;
-                } else {
-                    self.data[self.dp] -= 1;
+            Instruction::Add(delta) => {
+                self.apply_delta(self.dp, delta)?;
+                self.stats.add_count += 1;
+            }
+            Instruction::SetZero => {
+                self.data[self.dp] = 0;
+                self.stats.set_zero_count += 1;
+            }
+            Instruction::MulAdd(ops) => {
+                if self.data[self.dp] != 0 {
+                    let factor = self.data[self.dp] as i64;
+                    for (offset, delta) in ops {
+                        let idx = self.ensure_offset(offset);
+                        self.apply_delta(idx, factor * delta)?;
+                    }
+                    self.data[self.dp] = 0;
                 }
+                self.stats.mul_add_count += 1;
             }
             Instruction::Out => {
-                output.push(self.data[self.dp]);
+                output.write_all(&[self.data[self.dp] as u8])?;
+                self.stats.out_count += 1;
             }
             Instruction::In => {
-                if let Some(b) = input.pop_front() {
-                    self.data[self.dp] = b;
+                let mut byte = [0u8; 1];
+                let read = input.read(&mut byte)?;
+                if read == 1 {
+                    self.data[self.dp] = byte[0] as u32;
                 } else {
-                    self.data[self.dp] = 0;
+                    match self.config.eof {
+                        EofBehavior::Unchanged => {}
+                        EofBehavior::Zero => {
+                            self.data[self.dp] = 0;
+                        }
+                        EofBehavior::NegOne => {
+                            self.data[self.dp] = self.config.cell_width.max();
+                        }
+                    }
                 }
+                self.stats.in_count += 1;
             }
             Instruction::Jump(dest) => {
                 let do_jump = self.data[self.dp] == 0;
                 if do_jump {
                     self.ip = dest;
                 }
+                self.stats.jump_count += 1;
             }
             Instruction::Loop(dest) => {
                 let do_loop = self.data[self.dp] != 0;
                 if do_loop {
                     self.ip = dest;
                 }
+                self.stats.loop_count += 1;
             }
             Instruction::None => {
-                return true;
+                return Ok(true);
             }
         }
+        self.stats.steps += 1;
+        self.stats.peak_tape_size = self.stats.peak_tape_size.max(self.data.len());
         self.ip += 1;
-        return self.instructions[self.ip as usize] == Instruction::None;
+        return Ok(self.instructions[self.ip as usize] == Instruction::None);
     }
 
     /// Runs the VM to None. It is assumed to be in a valid state.
-    pub fn run(&mut self, input: &mut VecDeque<u8>, output: &mut Vec<u8>) {
-        while !self.step(input, output) {}
+    pub fn run<R: std::io::Read, W: std::io::Write>(
+        &mut self,
+        input: &mut R,
+        output: &mut W,
+    ) -> Result<(), VmError> {
+        while !self.step(input, output)? {}
+        return Ok(());
+    }
+}
+
+/// A naive, byte-by-byte reference interpreter with no IR or optimization
+/// passes, used to check the optimizing VM against. The tape is
+/// two-directional and unbounded in both directions, matching
+/// `VM::ensure_offset`'s behavior, so programs that move left of their
+/// starting cell don't panic here either.
+///
+/// Lives outside `mod tests` (but still `#[cfg(any(test, bench))]`, so it
+/// never ships in a real build) so `benches/bench.rs` can reuse the exact
+/// same reference implementation the unit tests check against, rather than
+/// keeping a second copy that can drift out of sync.
+#[cfg(any(test, bench))]
+pub(crate) fn naive_interpret(src: &str, input: &[u8]) -> Vec<u8> {
+    let code: Vec<char> = src.chars().filter(|c| "+-><.,[]".contains(*c)).collect();
+    let mut tape: std::collections::HashMap<i64, u8> = std::collections::HashMap::new();
+    let mut dp: i64 = 0;
+    let mut ip: usize = 0;
+    let mut input = input.iter();
+    let mut output: Vec<u8> = Vec::new();
+
+    while ip < code.len() {
+        match code[ip] {
+            '>' => dp += 1,
+            '<' => dp -= 1,
+            '+' => {
+                let cell = tape.entry(dp).or_insert(0);
+                *cell = cell.wrapping_add(1);
+            }
+            '-' => {
+                let cell = tape.entry(dp).or_insert(0);
+                *cell = cell.wrapping_sub(1);
+            }
+            '.' => output.push(*tape.get(&dp).unwrap_or(&0)),
+            ',' => {
+                tape.insert(dp, *input.next().unwrap_or(&0));
+            }
+            '[' if *tape.get(&dp).unwrap_or(&0) == 0 => {
+                let mut depth = 1;
+                while depth > 0 {
+                    ip += 1;
+                    match code[ip] {
+                        '[' => depth += 1,
+                        ']' => depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            ']' if *tape.get(&dp).unwrap_or(&0) != 0 => {
+                let mut depth = 1;
+                while depth > 0 {
+                    ip -= 1;
+                    match code[ip] {
+                        ']' => depth += 1,
+                        '[' => depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+        ip += 1;
+    }
+    return output;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vm_interpret(src: &str, input: &[u8]) -> Vec<u8> {
+        let mut vm = VM::construct(src, VmConfig::default()).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        vm.run(&mut std::io::Cursor::new(input.to_vec()), &mut output).unwrap();
+        return output;
+    }
+
+    fn assert_matches_naive(src: &str, input: &[u8]) {
+        assert_eq!(naive_interpret(src, input), vm_interpret(src, input));
+    }
+
+    #[test]
+    fn hello_world_matches_naive() {
+        let src = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+        assert_matches_naive(src, &[]);
+    }
+
+    #[test]
+    fn set_zero_idiom_matches_naive() {
+        assert_matches_naive("+++++[-].", &[]);
+    }
+
+    #[test]
+    fn mul_add_idiom_matches_naive() {
+        assert_matches_naive("+++++[>++++<-]>.", &[]);
+    }
+
+    #[test]
+    fn negative_offset_tape_growth_matches_naive() {
+        assert_matches_naive(">>><<<<+.", &[]);
+    }
+
+    #[test]
+    fn echoes_input_byte_matches_naive() {
+        assert_matches_naive(",.", &[65]);
+    }
+
+    fn run(overflow: Overflow, cell_width: CellWidth, src: &str) -> Vec<u8> {
+        let config = VmConfig {
+            cell_width,
+            overflow,
+            eof: EofBehavior::Zero,
+        };
+        let mut vm = VM::construct(src, config).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        vm.run(&mut std::io::empty(), &mut output).unwrap();
+        return output;
+    }
+
+    #[test]
+    fn saturating_run_clamps_to_max() {
+        let src = "+".repeat(300) + ".";
+        assert_eq!(run(Overflow::Saturate, CellWidth::U8, &src), vec![0xff]);
+    }
+
+    #[test]
+    fn halting_run_stops_before_output() {
+        let src = "+".repeat(300) + ".";
+        let config = VmConfig {
+            cell_width: CellWidth::U8,
+            overflow: Overflow::Halt,
+            eof: EofBehavior::Zero,
+        };
+        let mut vm = VM::construct(&src, config).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        let result = vm.run(&mut std::io::empty(), &mut output);
+        assert!(matches!(result, Err(VmError::Overflow)));
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn wrapping_run_keeps_full_magnitude_for_wide_cells() {
+        // A coalesced run's true magnitude (1000) must survive, not just its
+        // value truncated to a single byte, since `Out` alone can't tell
+        // 1000 apart from anything else that's 232 mod 256.
+        let config = VmConfig {
+            cell_width: CellWidth::U32,
+            overflow: Overflow::Wrap,
+            eof: EofBehavior::Zero,
+        };
+        let mut vm = VM::construct(&"+".repeat(1000), config).unwrap();
+        vm.run(&mut std::io::empty(), &mut std::io::sink()).unwrap();
+        assert_eq!(vm.data[vm.dp], 1000);
+    }
+
+    #[test]
+    fn mixed_sign_run_matches_stepwise_saturation() {
+        // 256 `+` saturate the cell at 255, then 3 `-` step it back down to
+        // 252; a same-direction-only coalescing must not net these to a
+        // single no-op delta and leave the cell unchanged.
+        let src = "+".repeat(256) + "---.";
+        assert_eq!(run(Overflow::Saturate, CellWidth::U8, &src), vec![252]);
     }
 }
@@ -1,4 +1,5 @@
 mod cli;
+mod encoding;
 mod lang;
 
 fn main() {
@@ -11,8 +12,14 @@ fn main() {
             cli::CliError::File(e) => {
                 println!("{:?}", e);
             }
-            cli::CliError::Syntax => {
-                println!("Syntax error.");
+            cli::CliError::Syntax(msg) => {
+                println!("{}", msg);
+            }
+            cli::CliError::Runtime(msg) => {
+                println!("{}", msg);
+            }
+            cli::CliError::Encoding(msg) => {
+                println!("{}", msg);
             }
         },
     }